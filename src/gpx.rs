@@ -87,6 +87,33 @@ pub struct Segment<'a> {
     // extensions
 }
 
+#[derive(Debug, XmlRead)]
+#[xml(tag = "extensions")]
+pub struct Extensions<'a> {
+    #[xml(child = "gpxtpx:TrackPointExtension")]
+    pub track_point_extension: Option<TrackPointExtension<'a>>,
+}
+
+/// The `gpxtpx:TrackPointExtension` element from the Garmin TrackPointExtension v1/v2 schema,
+/// as embedded in `<trkpt><extensions>` by most GPS watches and bike computers.
+#[derive(Debug, XmlRead)]
+#[xml(tag = "gpxtpx:TrackPointExtension")]
+pub struct TrackPointExtension<'a> {
+    #[xml(flatten_text = "gpxtpx:atemp")]
+    pub temperature: Option<Cow<'a, str>>,
+
+    #[xml(flatten_text = "gpxtpx:hr")]
+    pub heart_rate: Option<Cow<'a, str>>,
+
+    #[xml(flatten_text = "gpxtpx:cad")]
+    pub cadence: Option<Cow<'a, str>>,
+
+    // power is not part of the Garmin schema proper, but many devices (e.g. bike computers)
+    // stick it in here anyway.
+    #[xml(flatten_text = "power")]
+    pub power: Option<Cow<'a, str>>,
+}
+
 /*
 #[derive(Debug, XmlRead)]
 #[xml(tag = "copyright")]
@@ -123,6 +150,9 @@ pub struct Point<'a> {
 
     #[xml(attr = "lon")]
     pub longitude: Cow<'a, str>,
+
+    #[xml(child = "extensions")]
+    pub extensions: Option<Extensions<'a>>,
 }
 
 // bounds
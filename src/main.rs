@@ -1,8 +1,11 @@
 use anyhow::{bail, Context, Result};
 use clap::Parser;
+use flate2::read::GzDecoder;
+use serde::Serialize;
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read as IoRead;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use strong_xml::XmlRead;
@@ -12,7 +15,8 @@ use time::format_description::well_known::Rfc3339;
 mod gpx;
 mod units;
 
-use crate::units::{Meters, Feet, Miles};
+use crate::units::{Meters, Unit, UnitSystem};
+use crate::units::Duration as FmtDuration;
 
 #[derive(Debug, Parser)]
 #[command(about, version)]
@@ -56,12 +60,63 @@ struct Args {
     /// fix, and you will want to discard these to avoid incorrect elevation data.
     #[arg(long)]
     filter_ele_below: Option<Meters>,
+
+    /// Use Vincenty's inverse formula on the WGS84 ellipsoid for distance calculations, instead
+    /// of great-circle distance on a sphere. More accurate, but slower, and falls back to
+    /// great-circle distance for near-antipodal points where the iteration does not converge.
+    #[arg(long)]
+    geodesic: bool,
+
+    /// Subdivide each segment into consecutive windows of this many seconds, and report the
+    /// usual stats per window instead of once per segment. Conflicts with --bin-distance.
+    #[arg(long, value_parser = duration_secs, conflicts_with = "bin_distance")]
+    bin_time: Option<Duration>,
+
+    /// Subdivide each segment into consecutive windows of this many meters, and report the usual
+    /// stats per window instead of once per segment. Conflicts with --bin-time.
+    #[arg(long, conflicts_with = "bin_time")]
+    bin_distance: Option<Meters>,
+
+    /// Output format: human-readable text, or a machine-readable JSON document.
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+
+    /// Unit system to display the computed elevations and distances in. "auto" picks metric or
+    /// imperial based on the process locale. The parameters echoed at startup, including the min
+    /// moving speed, are always shown in meters and m/s.
+    #[arg(long, value_enum, default_value = "auto")]
+    units: UnitSystem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Format {
+    Text,
+    Json,
 }
 
 fn duration_secs(s: &str) -> Result<Duration> {
     Ok(Duration::seconds(s.parse()?))
 }
 
+/// Read a GPX file to a string, transparently decompressing it if it's gzipped. Files are
+/// treated as gzip if they're named `*.gz`, or if their contents start with the gzip magic bytes
+/// (1f 8b) regardless of name, in case they've been renamed.
+fn read_gpx_file(path: &Path) -> Result<String> {
+    let raw = fs::read(path).context("failed to read file")?;
+
+    let is_gzip = path.extension().is_some_and(|ext| ext == "gz")
+        || raw.starts_with(&[0x1f, 0x8b]);
+
+    if is_gzip {
+        let mut decoder = GzDecoder::new(&raw[..]);
+        let mut out = String::new();
+        decoder.read_to_string(&mut out).context("failed to decompress gzip data")?;
+        Ok(out)
+    } else {
+        String::from_utf8(raw).context("file is not valid UTF-8")
+    }
+}
+
 #[derive(Debug)]
 struct Track {
     name: String,
@@ -73,18 +128,76 @@ struct Segment {
     points: Vec<Point>,
 }
 
+/// Metadata about one input file, as reported in `--format json` output.
+#[derive(Debug, Serialize)]
+struct FileMeta {
+    path: String,
+    name: Option<String>,
+    creator: String,
+    num_tracks: usize,
+    num_segments: usize,
+}
+
+/// The full set of computed metrics, for `--format json` output.
+#[derive(Debug, Serialize)]
+struct Report {
+    files: Vec<FileMeta>,
+    tracks: Vec<TrackStats>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrackStats {
+    name: String,
+    segments: Vec<SegmentStats>,
+}
+
+#[derive(Debug, Serialize)]
+struct SegmentStats {
+    /// 1-based index of the segment this bin belongs to, among the track's segments.
+    segment: usize,
+    /// 1-based index of this bin within its segment, or `None` if the segment was not split into
+    /// more than one bin (binning via `--bin-time` / `--bin-distance` was not in use, or the whole
+    /// segment fit in one bin) and this `SegmentStats` covers the whole segment.
+    bin: Option<usize>,
+    points: usize,
+    time_delta_mean_secs: f64,
+    time_delta_median_secs: f64,
+    time_delta_mode_secs: f64,
+    start_elevation_m: Option<f64>,
+    end_elevation_m: Option<f64>,
+    min_elevation_m: Option<f64>,
+    max_elevation_m: Option<f64>,
+    elevation_gain_m: f64,
+    total_distance_m: f64,
+    total_time_secs: f64,
+    moving_time_secs: f64,
+    average_heart_rate_bpm: Option<f64>,
+    max_heart_rate_bpm: Option<u16>,
+    average_cadence_rpm: Option<f64>,
+    max_cadence_rpm: Option<u16>,
+    min_temperature_c: Option<f64>,
+    max_temperature_c: Option<f64>,
+    average_power_w: Option<f64>,
+    normalized_power_w: Option<f64>,
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Point {
     lat: f64,
     lon: f64,
     ele: Option<Meters>,
     time: OffsetDateTime,
+    heart_rate: Option<u16>,
+    cadence: Option<u16>,
+    temperature: Option<f64>,
+    power: Option<u16>,
 }
 
 static HAVE_WARNED_ABOUT_TIMEZONE: AtomicBool = AtomicBool::new(false);
 
 impl Point {
     pub fn new(gpx: &gpx::Point<'_>) -> Result<Self> {
+        let tpx = gpx.extensions.as_ref().and_then(|e| e.track_point_extension.as_ref());
         Ok(Self {
             lat: gpx.latitude.parse().context("invalid latitude")?,
             lon: gpx.longitude.parse().context("invalid longitude")?,
@@ -93,6 +206,22 @@ impl Point {
                 .map(Meters::from_str)
                 .transpose()
                 .context("invalid altitude")?,
+            heart_rate: tpx.and_then(|t| t.heart_rate.as_deref())
+                .map(|s| s.parse())
+                .transpose()
+                .context("invalid heart rate")?,
+            cadence: tpx.and_then(|t| t.cadence.as_deref())
+                .map(|s| s.parse())
+                .transpose()
+                .context("invalid cadence")?,
+            temperature: tpx.and_then(|t| t.temperature.as_deref())
+                .map(|s| s.parse())
+                .transpose()
+                .context("invalid temperature")?,
+            power: tpx.and_then(|t| t.power.as_deref())
+                .map(|s| s.parse())
+                .transpose()
+                .context("invalid power")?,
             time: OffsetDateTime::parse(&gpx.time, &Rfc3339)
                 .or_else(|e| {
                     // HACK: try the time with 'Z' appended, for bad GPX files missing timezone
@@ -111,33 +240,51 @@ impl Point {
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
-
-    println!("{} v{} by {}",
-        env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), env!("CARGO_PKG_AUTHORS"));
-    println!("parameters:");
-    println!("  min elevation gain: {}", args.min_elevation_gain);
-    println!("  min distance: {}", args.min_distance);
+    let mut args = Args::parse();
+    args.units = args.units.resolve();
+    let text = args.format == Format::Text;
+
+    if text {
+        println!("{} v{} by {}",
+            env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), env!("CARGO_PKG_AUTHORS"));
+        println!("parameters:");
+        println!("  min elevation gain: {}", args.min_elevation_gain);
+        println!("  min distance: {}", args.min_distance);
+    }
 
     let min_moving_speed = args.min_distance.0
         / args.standstill_time.as_seconds_f64();
-    println!("  min moving speed: {} m/s", min_moving_speed);
+    if text {
+        println!("  min moving speed: {} m/s", min_moving_speed);
+    }
 
+    let mut files = vec![];
     let mut tracks = Vec::<Track>::with_capacity(args.input_paths.len());
     for path in args.input_paths {
-        let input = fs::read_to_string(&path)
+        let input = read_gpx_file(&path)
             .with_context(|| format!("failed to read GPX file to string: {:?}", path))?;
 
         let gpx = gpx::Gpx::from_str(&input)
             .with_context(|| format!("failed to parse GPX file {:?}", path))?;
 
         let file_name = gpx.metadata.as_ref().and_then(|m| m.name.as_deref());
+        let num_segments = gpx.tracks.iter().map(|t| t.segments.len()).sum::<usize>();
+
+        if text {
+            println!("file {:?}:", path);
+            println!("  name: {}", file_name.unwrap_or("<unnamed>"));
+            println!("  creator: {}", gpx.creator);
+            println!("  tracks: {}", gpx.tracks.len());
+            println!("  segments: {}", num_segments);
+        }
 
-        println!("file {:?}:", path);
-        println!("  name: {}", file_name.unwrap_or("<unnamed>"));
-        println!("  creator: {}", gpx.creator);
-        println!("  tracks: {}", gpx.tracks.len());
-        println!("  segments: {}", gpx.tracks.iter().map(|t| t.segments.len()).sum::<usize>());
+        files.push(FileMeta {
+            path: path.display().to_string(),
+            name: file_name.map(str::to_owned),
+            creator: gpx.creator.to_string(),
+            num_tracks: gpx.tracks.len(),
+            num_segments,
+        });
 
         for gpx_track in gpx.tracks {
             let track = if args.join_tracks {
@@ -199,136 +346,317 @@ fn main() -> Result<()> {
         }
     }
 
-    println!("---");
+    if text {
+        println!("---");
+    }
 
-    for (tnum, track) in tracks.into_iter().enumerate() {
-        println!("track {}: {}", tnum + 1, track.name);
+    let mut track_stats = Vec::with_capacity(tracks.len());
 
-        if args.join_segments {
-            println!("  (all segments joined)");
+    for (tnum, track) in tracks.into_iter().enumerate() {
+        if text {
+            println!("track {}: {}", tnum + 1, track.name);
+            if args.join_segments {
+                println!("  (all segments joined)");
+            }
         }
 
+        let mut segments = vec![];
+
         for (snum, seg) in track.segments.into_iter().enumerate() {
-            println!("  segment {}:", snum + 1);
+            if text {
+                println!("  segment {}:", snum + 1);
+            }
 
-            let mut ele_start = Meters(std::f64::NAN);
-            let mut ele_min = Meters(std::f64::MAX);
-            let mut ele_max = Meters(std::f64::MIN);
-            let mut ele_end = Meters(std::f64::NAN);
-            let mut ele_gain = Meters(0.);
-            let mut ele_last: Option<Meters> = None;
+            let bins = bin_points(seg.points, args.bin_time, args.bin_distance, args.geodesic);
+            let binned = bins.len() > 1;
 
-            let mut dist_total = Meters(0.);
-            let mut dist_last: Option<Point> = None;
+            for (bnum, points) in bins.into_iter().enumerate() {
+                let indent = if binned {
+                    if text {
+                        println!("    bin {}:", bnum + 1);
+                    }
+                    "      "
+                } else {
+                    "    "
+                };
+                let bin = binned.then_some(bnum + 1);
+                if let Some(stats) =
+                    report_points(points, &args, min_moving_speed, text, indent, snum + 1, bin)?
+                {
+                    segments.push(stats);
+                }
+            }
+        }
 
-            let time_start: OffsetDateTime;
-            let mut time_end: OffsetDateTime;
-            let mut time_moving = Duration::seconds(0);
+        track_stats.push(TrackStats { name: track.name, segments });
+    }
 
-            if let Some(point) = seg.points.get(0) {
-                time_start = point.time;
-                time_end = point.time;
-            } else {
-                println!("    no points");
-                continue;
-            }
+    if args.format == Format::Json {
+        let report = Report { files, tracks: track_stats };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
 
-            println!("    points: {}", seg.points.len());
+    Ok(())
+}
 
-            let mut last_time = None;
-            let mut time_deltas = vec![];
+/// Subdivide a segment's points into consecutive windows by `bin_time` seconds or `bin_distance`
+/// meters (whichever is set), so the caller can report stats per-window instead of once for the
+/// whole segment. A window is closed at the first point at which its elapsed time or distance
+/// reaches or passes the limit, so it can span more than the limit. Returns the points unsplit if
+/// neither is set.
+fn bin_points(
+    points: Vec<Point>,
+    bin_time: Option<Duration>,
+    bin_distance: Option<Meters>,
+    geodesic: bool,
+) -> Vec<Vec<Point>> {
+    if bin_time.is_none() && bin_distance.is_none() {
+        return vec![points];
+    }
 
-            for point in seg.points {
-                time_end = point.time;
+    let mut bins = vec![];
+    let mut current = vec![];
+    let mut bin_start_time = None;
+    let mut bin_dist = Meters(0.);
+    let mut last_point: Option<Point> = None;
 
-                if let Some(t) = last_time {
-                    if point.time < t {
-                        bail!("time went backwards? {} -> {}", t, point.time);
-                    }
-                    let delta = point.time - t;
-                    time_deltas.push(delta);
-                }
-                last_time = Some(point.time);
-
-                // Distance smoothing.
-                let mut use_point = true;
-                if let Some(last) = dist_last {
-                    let (dist, time, speed) = dist_time_speed(&last, &point);
-                    if dist.0 >= args.min_distance.0 {
-                        dist_total.0 += dist.0;
-                        if speed >= min_moving_speed {
-                            time_moving += time;
-                        }
-                        dist_last = Some(point);
-                    } else {
-                        use_point = false;
-                    }
-                } else {
-                    dist_last = Some(point);
-                }
+    for point in points {
+        if bin_start_time.is_none() {
+            bin_start_time = Some(point.time);
+        }
+        if let Some(last) = last_point {
+            let (dist, _, _) = dist_time_speed(&last, &point, geodesic);
+            bin_dist.0 += dist.0;
+        }
+        last_point = Some(point);
+        current.push(point);
+
+        let elapsed = bin_start_time.map_or(Duration::seconds(0), |t| point.time - t);
+        let time_exceeded = bin_time.is_some_and(|bt| elapsed >= bt);
+        let dist_exceeded = bin_distance.is_some_and(|bd| bin_dist.0 >= bd.0);
+        if time_exceeded || dist_exceeded {
+            bins.push(std::mem::take(&mut current));
+            bin_start_time = None;
+            bin_dist = Meters(0.);
+        }
+    }
+    if !current.is_empty() {
+        bins.push(current);
+    }
+    bins
+}
 
-                // Elevation smoothing.
-                if let Some(e) = point.ele {
-                    if ele_start.0.is_nan() {
-                        ele_start = e;
-                    }
-                    if ele_min.0 > e.0 {
-                        ele_min = e;
-                    }
-                    if ele_max.0 < e.0 {
-                        ele_max = e;
-                    }
-                    ele_end = e;
+/// Compute and print the elevation/distance/time/biometric summary for one bin of a segment (or
+/// for the whole segment, if binning is not in use).
+fn report_points(
+    points: Vec<Point>,
+    args: &Args,
+    min_moving_speed: f64,
+    text: bool,
+    indent: &str,
+    segment: usize,
+    bin: Option<usize>,
+) -> Result<Option<SegmentStats>> {
+    let mut ele_start = Meters(std::f64::NAN);
+    let mut ele_min = Meters(std::f64::MAX);
+    let mut ele_max = Meters(std::f64::MIN);
+    let mut ele_end = Meters(std::f64::NAN);
+    let mut ele_gain = Meters(0.);
+    let mut ele_last: Option<Meters> = None;
+
+    let mut dist_total = Meters(0.);
+    let mut dist_last: Option<Point> = None;
+
+    let mut hr_sum = 0u64;
+    let mut hr_count = 0u64;
+    let mut hr_max = 0u16;
+
+    let mut cad_sum = 0u64;
+    let mut cad_count = 0u64;
+    let mut cad_max = 0u16;
+
+    let mut temp_min = std::f64::MAX;
+    let mut temp_max = std::f64::MIN;
+
+    let mut power_sum = 0u64;
+    let mut power_count = 0u64;
+    let mut power_samples: Vec<(OffsetDateTime, u16)> = vec![];
+
+    let time_start: OffsetDateTime;
+    let mut time_end: OffsetDateTime;
+    let mut time_moving = Duration::seconds(0);
+
+    if let Some(point) = points.get(0) {
+        time_start = point.time;
+        time_end = point.time;
+    } else {
+        if text {
+            println!("{}no points", indent);
+        }
+        return Ok(None);
+    }
 
-                    if let Some(Meters(last)) = ele_last {
-                        if use_point && (e.0 - last).abs() >= args.min_elevation_gain.0 {
-                            if e.0 > last {
-                                ele_gain.0 += e.0 - last;
-                            }
-                            ele_last = Some(e);
-                        }
-                    } else {
-                        ele_last = Some(e);
-                    }
+    let num_points = points.len();
+    if text {
+        println!("{}points: {}", indent, num_points);
+    }
+
+    let mut last_time = None;
+    let mut time_deltas = vec![];
+
+    for point in points {
+        time_end = point.time;
+
+        if let Some(t) = last_time {
+            if point.time < t {
+                bail!("time went backwards? {} -> {}", t, point.time);
+            }
+            let delta = point.time - t;
+            time_deltas.push(delta);
+        }
+        last_time = Some(point.time);
+
+        // Biometric stats. Points missing a given field are simply skipped for it, so
+        // partial data still produces a usable summary.
+        if let Some(hr) = point.heart_rate {
+            hr_sum += hr as u64;
+            hr_count += 1;
+            hr_max = hr_max.max(hr);
+        }
+        if let Some(cad) = point.cadence {
+            cad_sum += cad as u64;
+            cad_count += 1;
+            cad_max = cad_max.max(cad);
+        }
+        if let Some(temp) = point.temperature {
+            temp_min = temp_min.min(temp);
+            temp_max = temp_max.max(temp);
+        }
+        if let Some(power) = point.power {
+            power_sum += power as u64;
+            power_count += 1;
+            power_samples.push((point.time, power));
+        }
+
+        // Distance smoothing.
+        let mut use_point = true;
+        if let Some(last) = dist_last {
+            let (dist, time, speed) = dist_time_speed(&last, &point, args.geodesic);
+            if dist.0 >= args.min_distance.0 {
+                dist_total.0 += dist.0;
+                if speed >= min_moving_speed {
+                    time_moving += time;
                 }
+                dist_last = Some(point);
+            } else {
+                use_point = false;
             }
+        } else {
+            dist_last = Some(point);
+        }
 
-            if time_deltas.is_empty() {
-                time_deltas.push(Duration::default());
+        // Elevation smoothing.
+        if let Some(e) = point.ele {
+            if ele_start.0.is_nan() {
+                ele_start = e;
             }
-            time_deltas.sort();
-            let mean = time_deltas.iter()
-                .sum::<Duration>() / time_deltas.len() as u32;
-            let median = time_deltas[time_deltas.len() / 2];
-            let mut freq = BTreeMap::new();
-            for d in &time_deltas {
-                *freq.entry(d).or_insert(0) += 1;
+            if ele_min.0 > e.0 {
+                ele_min = e;
+            }
+            if ele_max.0 < e.0 {
+                ele_max = e;
+            }
+            ele_end = e;
+
+            if let Some(Meters(last)) = ele_last {
+                if use_point && (e.0 - last).abs() >= args.min_elevation_gain.0 {
+                    if e.0 > last {
+                        ele_gain.0 += e.0 - last;
+                    }
+                    ele_last = Some(e);
+                }
+            } else {
+                ele_last = Some(e);
             }
-            let mode = freq.iter().max_by(|(_, count1), (_, count2)| count1.cmp(count2)).unwrap().0;
-            println!("    point time deltas:");
-            println!("        mean:   {}s", mean.as_seconds_f64());
-            println!("        median: {}s", median.as_seconds_f64());
-            println!("        mode:   {}s", mode.as_seconds_f64());
-
-            println!("    starting elevation: {}", Feet(ele_start));
-            println!("    ending elevation: {}", Feet(ele_end));
-            println!("    min elevation: {}", Feet(ele_min));
-            println!("    max elevation: {}", Feet(ele_max));
-            println!("    elevation gain: {}", Feet(ele_gain));
-            println!("    total distance: {}", Miles(dist_total));
-            println!("    total time: {}", fmt_duration(time_end - time_start));
-            println!("    moving time: {}", fmt_duration(time_moving));
         }
     }
 
-    Ok(())
-}
+    if time_deltas.is_empty() {
+        time_deltas.push(Duration::default());
+    }
+    time_deltas.sort();
+    let mean = time_deltas.iter()
+        .sum::<Duration>() / time_deltas.len() as u32;
+    let median = time_deltas[time_deltas.len() / 2];
+    let mut freq = BTreeMap::new();
+    for d in &time_deltas {
+        *freq.entry(d).or_insert(0) += 1;
+    }
+    let mode = *freq.iter().max_by(|(_, count1), (_, count2)| count1.cmp(count2)).unwrap().0;
+
+    let has_ele = !ele_start.0.is_nan();
+    let average_heart_rate = (hr_count > 0).then(|| hr_sum as f64 / hr_count as f64);
+    let average_cadence = (cad_count > 0).then(|| cad_sum as f64 / cad_count as f64);
+    let has_temp = temp_min <= temp_max;
+    let average_power = (power_count > 0).then(|| power_sum as f64 / power_count as f64);
+
+    if text {
+        println!("{}point time deltas:", indent);
+        println!("{}    mean:   {}s", indent, mean.as_seconds_f64());
+        println!("{}    median: {}s", indent, median.as_seconds_f64());
+        println!("{}    mode:   {}s", indent, mode.as_seconds_f64());
+
+        println!("{}starting elevation: {}", indent, Unit::elevation(args.units, ele_start));
+        println!("{}ending elevation: {}", indent, Unit::elevation(args.units, ele_end));
+        println!("{}min elevation: {}", indent, Unit::elevation(args.units, ele_min));
+        println!("{}max elevation: {}", indent, Unit::elevation(args.units, ele_max));
+        println!("{}elevation gain: {}", indent, Unit::elevation(args.units, ele_gain));
+        println!("{}total distance: {}", indent, Unit::distance(args.units, dist_total));
+        println!("{}total time: {}", indent, FmtDuration(time_end - time_start));
+        println!("{}moving time: {}", indent, FmtDuration(time_moving));
+
+        if let Some(avg) = average_heart_rate {
+            println!("{}average heart rate: {:.0} bpm", indent, avg);
+            println!("{}max heart rate: {} bpm", indent, hr_max);
+        }
+        if let Some(avg) = average_cadence {
+            println!("{}average cadence: {:.0} rpm", indent, avg);
+            println!("{}max cadence: {} rpm", indent, cad_max);
+        }
+        if has_temp {
+            println!("{}min temperature: {:.1} C", indent, temp_min);
+            println!("{}max temperature: {:.1} C", indent, temp_max);
+        }
+        if let Some(avg) = average_power {
+            println!("{}average power: {:.0} W", indent, avg);
+            println!("{}normalized power: {:.0} W", indent, normalized_power(&power_samples));
+        }
+    }
 
-fn fmt_duration(d: Duration) -> String {
-    let hours = d.whole_hours();
-    let from_hours = Duration::hours(hours);
-    let mins = (d - from_hours).whole_minutes();
-    format!("{}:{:02}", hours, mins)
+    Ok(Some(SegmentStats {
+        segment,
+        bin,
+        points: num_points,
+        time_delta_mean_secs: mean.as_seconds_f64(),
+        time_delta_median_secs: median.as_seconds_f64(),
+        time_delta_mode_secs: mode.as_seconds_f64(),
+        start_elevation_m: has_ele.then_some(ele_start.0),
+        end_elevation_m: has_ele.then_some(ele_end.0),
+        min_elevation_m: has_ele.then_some(ele_min.0),
+        max_elevation_m: has_ele.then_some(ele_max.0),
+        elevation_gain_m: ele_gain.0,
+        total_distance_m: dist_total.0,
+        total_time_secs: (time_end - time_start).as_seconds_f64(),
+        moving_time_secs: time_moving.as_seconds_f64(),
+        average_heart_rate_bpm: average_heart_rate,
+        max_heart_rate_bpm: (hr_count > 0).then_some(hr_max),
+        average_cadence_rpm: average_cadence,
+        max_cadence_rpm: (cad_count > 0).then_some(cad_max),
+        min_temperature_c: has_temp.then_some(temp_min),
+        max_temperature_c: has_temp.then_some(temp_max),
+        average_power_w: average_power,
+        normalized_power_w: (power_count > 0).then(|| normalized_power(&power_samples)),
+    }))
 }
 
 fn distance(a: &Point, b: &Point) -> Meters {
@@ -348,8 +676,103 @@ fn distance(a: &Point, b: &Point) -> Meters {
     Meters(2. * R * a.sqrt().asin())
 }
 
-fn dist_time_speed(a: &Point, b: &Point) -> (Meters, Duration, f64) {
-    let dist = distance(a, b);
+// WGS84 ellipsoid parameters.
+const WGS84_A: f64 = 6378137.0; // semi-major axis, in meters
+const WGS84_F: f64 = 1. / 298.257223563; // flattening
+const WGS84_B: f64 = (1. - WGS84_F) * WGS84_A; // semi-minor axis, in meters
+
+/// Ellipsoid distance via Vincenty's inverse formula on the WGS84 ellipsoid. Falls back to
+/// great-circle `distance()` if the iteration fails to converge, which happens for near-antipodal
+/// points.
+fn geodesic_distance(a: &Point, b: &Point) -> Meters {
+    if a.lat == b.lat && a.lon == b.lon {
+        return Meters(0.);
+    }
+
+    const P: f64 = std::f64::consts::PI / 180.;
+    let l = (b.lon - a.lon) * P;
+
+    let u1 = ((1. - WGS84_F) * (a.lat * P).tan()).atan();
+    let u2 = ((1. - WGS84_F) * (b.lat * P).tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    let mut cos_sq_alpha;
+    let mut sin_sigma;
+    let mut cos_sigma;
+    let mut sigma;
+    let mut cos_2sigma_m;
+
+    for _ in 0..200 {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        sin_sigma = (
+            (cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2)
+        ).sqrt();
+        if sin_sigma == 0. {
+            // coincident points
+            return Meters(0.);
+        }
+        cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        cos_sq_alpha = 1. - sin_alpha.powi(2);
+        cos_2sigma_m = if cos_sq_alpha == 0. {
+            // equatorial line
+            0.
+        } else {
+            cos_sigma - 2. * sin_u1 * sin_u2 / cos_sq_alpha
+        };
+        let c = (WGS84_F / 16.) * cos_sq_alpha * (4. + WGS84_F * (4. - 3. * cos_sq_alpha));
+        let lambda_next = l + (1. - c) * WGS84_F * sin_alpha * (
+            sigma + c * sin_sigma * (
+                cos_2sigma_m + c * cos_sigma * (-1. + 2. * cos_2sigma_m.powi(2))
+            )
+        );
+        if (lambda - lambda_next).abs() < 1e-12 {
+            let u_sq = cos_sq_alpha * (WGS84_A.powi(2) - WGS84_B.powi(2)) / WGS84_B.powi(2);
+            let big_a = 1. + (u_sq / 16384.) * (4096. + u_sq * (-768. + u_sq * (320. - 175. * u_sq)));
+            let big_b = (u_sq / 1024.) * (256. + u_sq * (-128. + u_sq * (74. - 47. * u_sq)));
+            let delta_sigma = big_b * sin_sigma * (
+                cos_2sigma_m + (big_b / 4.) * (
+                    cos_sigma * (-1. + 2. * cos_2sigma_m.powi(2))
+                    - (big_b / 6.) * cos_2sigma_m * (-3. + 4. * sin_sigma.powi(2))
+                        * (-3. + 4. * cos_2sigma_m.powi(2))
+                )
+            );
+            return Meters(WGS84_B * big_a * (sigma - delta_sigma));
+        }
+        lambda = lambda_next;
+    }
+
+    // iteration did not converge within 200 steps (near-antipodal points); fall back.
+    distance(a, b)
+}
+
+/// Normalized-power-style weighting: average power over trailing 30-second windows, then take
+/// the fourth-power mean of those averages (rewarding variability over a flat effort at the same
+/// average wattage), as popularized by TrainingPeaks.
+fn normalized_power(samples: &[(OffsetDateTime, u16)]) -> f64 {
+    if samples.is_empty() {
+        return 0.;
+    }
+    const WINDOW: Duration = Duration::seconds(30);
+    let mut fourth_powers = vec![];
+    let mut start = 0;
+    for end in 0..samples.len() {
+        while samples[end].0 - samples[start].0 > WINDOW {
+            start += 1;
+        }
+        let window = &samples[start..=end];
+        let avg = window.iter().map(|(_, w)| *w as f64).sum::<f64>() / window.len() as f64;
+        fourth_powers.push(avg.powi(4));
+    }
+    (fourth_powers.iter().sum::<f64>() / fourth_powers.len() as f64).powf(0.25)
+}
+
+fn dist_time_speed(a: &Point, b: &Point, geodesic: bool) -> (Meters, Duration, f64) {
+    let dist = if geodesic { geodesic_distance(a, b) } else { distance(a, b) };
     let time = if a.time > b.time { a.time - b.time } else { b.time - a.time };
     let speed = dist.0 / time.as_seconds_f64().abs();
     (dist, time, speed)
@@ -1,6 +1,43 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::str::FromStr;
 
+/// Which unit system to render values in. Selected by the user with `--units`. `main()` resolves
+/// `Auto` to a concrete system once, up front, via `UnitSystem::resolve`, before threading it
+/// through to the rendered elevations and distances of each segment. Speeds and the parameters
+/// echoed at startup are not rendered through it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum UnitSystem {
+    Metric,
+    Imperial,
+    Auto,
+}
+
+impl UnitSystem {
+    /// Resolve `Auto` to `Metric` or `Imperial` based on the process locale (`LC_ALL`,
+    /// `LC_MEASUREMENT`, then `LANG`): Imperial if the locale names the US, Liberia, or Myanmar
+    /// (the remaining holdouts that use it), or if no locale is set at all, matching this tool's
+    /// historical default of always printing Feet/Miles; Metric otherwise. `Metric`/`Imperial`
+    /// are returned unchanged.
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto => {
+                let locale = std::env::var("LC_ALL")
+                    .or_else(|_| std::env::var("LC_MEASUREMENT"))
+                    .or_else(|_| std::env::var("LANG"))
+                    .unwrap_or_default();
+                if locale.is_empty()
+                    || locale.contains("_US") || locale.contains("_LR") || locale.contains("_MM")
+                {
+                    Self::Imperial
+                } else {
+                    Self::Metric
+                }
+            }
+            other => other,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Unit {
     Meters(Meters),
@@ -9,6 +46,41 @@ pub enum Unit {
     Miles(Miles),
 }
 
+impl Unit {
+    /// Pick the most sensible concrete unit to display a distance in, given the unit system and
+    /// the magnitude of the value: meters under 1 km and kilometers above, or feet under 1000 ft
+    /// (about 305 m) and miles above.
+    pub fn distance(system: UnitSystem, meters: Meters) -> Self {
+        match system.resolve() {
+            UnitSystem::Metric => {
+                if meters.0.abs() < 1000. {
+                    Self::Meters(meters)
+                } else {
+                    Self::Kilometers(Kilometers(meters))
+                }
+            }
+            UnitSystem::Imperial => {
+                if meters.0.abs() * 3.2808399 < 1000. {
+                    Self::Feet(Feet(meters))
+                } else {
+                    Self::Miles(Miles(meters))
+                }
+            }
+            UnitSystem::Auto => unreachable!("resolve() never returns Auto"),
+        }
+    }
+
+    /// Pick the concrete unit to display an elevation in. Unlike `distance`, this never promotes
+    /// to kilometers/miles, since elevations are rarely large enough to warrant it.
+    pub fn elevation(system: UnitSystem, meters: Meters) -> Self {
+        match system.resolve() {
+            UnitSystem::Metric => Self::Meters(meters),
+            UnitSystem::Imperial => Self::Feet(Feet(meters)),
+            UnitSystem::Auto => unreachable!("resolve() never returns Auto"),
+        }
+    }
+}
+
 impl Display for Unit {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
@@ -65,3 +137,17 @@ impl Display for Kilometers {
         write!(f, "{:.1} km", (self.0).0 * 0.001)
     }
 }
+
+/// A duration, rendered as `H:MM`. Moved here from `main.rs` so that all display formatting for
+/// computed metrics lives in one place.
+#[derive(Debug, Copy, Clone)]
+pub struct Duration(pub time::Duration);
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let hours = self.0.whole_hours();
+        let from_hours = time::Duration::hours(hours);
+        let mins = (self.0 - from_hours).whole_minutes();
+        write!(f, "{}:{:02}", hours, mins)
+    }
+}